@@ -0,0 +1,38 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("token_id already claimed")]
+    Claimed {},
+
+    #[error("Cannot set approval that is already expired")]
+    Expired {},
+
+    #[error("royalty_rate_bps must be between 0 and 10000")]
+    InvalidRoyaltyRate {},
+
+    #[error("royalty_payment_address and royalty_rate_bps must be set together")]
+    InvalidRoyaltyInput {},
+
+    #[error("contract status forbids this action")]
+    ContractStatusForbids {},
+
+    #[error("a swap with this id already exists")]
+    SwapAlreadyExists {},
+
+    #[error("swap has expired")]
+    SwapExpired {},
+
+    #[error("funds sent do not match the swap price")]
+    InvalidSwapFunds {},
+
+    #[error("cw20-denominated swaps are not yet supported")]
+    UnsupportedSwapPaymentToken {},
+}
@@ -0,0 +1,1910 @@
+use cosmwasm_std::{Addr, BankMsg, Coin, DepsMut, Env, MessageInfo, Response};
+use cw721::{Cw721ReceiveMsg, CustomMsg, Expiration};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MintMsg};
+use crate::state::{
+    Approval, ContractStatus, Cw721Contract, RoyaltyInfo, RoyaltyInfoResponse, Swap, SwapType,
+    TokenInfo, TxAction,
+};
+
+impl<'a, MintExt, ResponseExt, InstantiateExt, ExecuteExt, QueryExt>
+    Cw721Contract<'a, MintExt, ResponseExt, InstantiateExt, ExecuteExt, QueryExt>
+where
+    MintExt: Serialize + DeserializeOwned + Clone,
+    InstantiateExt: CustomMsg + DeserializeOwned,
+    ExecuteExt: CustomMsg,
+    QueryExt: CustomMsg,
+{
+    pub fn instantiate(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        let minter = deps.api.addr_validate(&msg.minter)?;
+        self.minter.save(deps.storage, &minter)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "instantiate")
+            .add_attribute("minter", minter))
+    }
+
+    pub fn execute(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: ExecuteMsg<MintExt, ExecuteExt>,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        match msg {
+            ExecuteMsg::Mint(msg) => self.mint(deps, env, info, msg),
+            ExecuteMsg::TransferNft {
+                recipient,
+                token_id,
+            } => self.transfer_nft(deps, env, info, recipient, token_id),
+            ExecuteMsg::SendNft {
+                contract,
+                token_id,
+                msg,
+            } => self.send_nft(deps, env, info, contract, token_id, msg),
+            ExecuteMsg::Approve {
+                spender,
+                token_id,
+                expires,
+            } => self.approve(deps, env, info, spender, token_id, expires),
+            ExecuteMsg::Revoke { spender, token_id } => {
+                self.revoke(deps, env, info, spender, token_id)
+            }
+            ExecuteMsg::ApproveAll { operator, expires } => {
+                self.approve_all(deps, env, info, operator, expires)
+            }
+            ExecuteMsg::RevokeAll { operator } => self.revoke_all(deps, env, info, operator),
+            ExecuteMsg::Burn { token_id } => self.burn(deps, env, info, token_id),
+            ExecuteMsg::SetDefaultRoyalty {
+                payment_address,
+                royalty_rate_bps,
+            } => self.set_default_royalty(deps, info, payment_address, royalty_rate_bps),
+            ExecuteMsg::SetContractStatus { level } => {
+                self.set_contract_status_exec(deps, info, level)
+            }
+            ExecuteMsg::CreateSwap {
+                id,
+                token_id,
+                price,
+                payment_token,
+                swap_type,
+                expires,
+            } => self.create_swap(
+                deps, env, info, id, token_id, price, payment_token, swap_type, expires,
+            ),
+            ExecuteMsg::FinishSwap { id } => self.finish_swap(deps, env, info, id),
+            ExecuteMsg::CancelSwap { id } => self.cancel_swap(deps, env, info, id),
+            ExecuteMsg::Extension { msg: _ } => Ok(Response::new().add_attribute("action", "extension")),
+        }
+    }
+
+    pub fn mint(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: MintMsg<MintExt>,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+        self.assert_can_mint(deps.storage)?;
+
+        if self.tokens.has(deps.storage, &msg.token_id) {
+            return Err(ContractError::Claimed {});
+        }
+
+        if matches!(msg.royalty_rate_bps, Some(bps) if bps > 10_000) {
+            return Err(ContractError::InvalidRoyaltyRate {});
+        }
+        let royalty = match (msg.royalty_payment_address, msg.royalty_rate_bps) {
+            (Some(addr), Some(bps)) => Some(RoyaltyInfo {
+                payment_address: deps.api.addr_validate(&addr)?,
+                royalty_rate_bps: bps,
+            }),
+            (None, None) => None,
+            _ => return Err(ContractError::InvalidRoyaltyInput {}),
+        };
+
+        let mint_run_info = match msg.mint_run {
+            Some(mint_run) => {
+                let serial_number =
+                    self.next_serial_number(deps.storage, mint_run, msg.mint_run_size)?;
+                Some(crate::state::MintRunInfo {
+                    minter: info.sender.clone(),
+                    mint_run: Some(mint_run),
+                    serial_number,
+                    quantity_minted_in_run: Some(serial_number),
+                })
+            }
+            None => None,
+        };
+
+        let owner = deps.api.addr_validate(&msg.owner)?;
+        let token = TokenInfo {
+            owner: owner.clone(),
+            approvals: vec![],
+            token_uri: msg.token_uri,
+            extension: msg.extension,
+            royalty,
+            mint_run_info,
+        };
+        self.tokens.save(deps.storage, &msg.token_id, &token)?;
+        self.increment_tokens(deps.storage)?;
+        self.append_tx(
+            deps.storage,
+            TxAction::Mint,
+            &msg.token_id,
+            None,
+            Some(owner.clone()),
+            &env.block,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "mint")
+            .add_attribute("minter", info.sender)
+            .add_attribute("owner", owner)
+            .add_attribute("token_id", msg.token_id))
+    }
+
+    pub fn transfer_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        token_id: String,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        let recipient_addr = deps.api.addr_validate(&recipient)?;
+        self._transfer_nft(
+            deps,
+            &env,
+            &info,
+            &recipient_addr,
+            &token_id,
+            TxAction::Transfer,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "transfer_nft")
+            .add_attribute("sender", info.sender)
+            .add_attribute("recipient", recipient)
+            .add_attribute("token_id", token_id))
+    }
+
+    pub fn send_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+        msg: cosmwasm_std::Binary,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        let contract_addr = deps.api.addr_validate(&contract)?;
+        self._transfer_nft(deps, &env, &info, &contract_addr, &token_id, TxAction::Send)?;
+
+        let receive_msg = Cw721ReceiveMsg {
+            sender: info.sender.to_string(),
+            token_id: token_id.clone(),
+            msg,
+        };
+
+        Ok(Response::new()
+            .add_message(receive_msg.into_cosmos_msg(contract)?)
+            .add_attribute("action", "send_nft")
+            .add_attribute("sender", info.sender)
+            .add_attribute("recipient", contract_addr)
+            .add_attribute("token_id", token_id))
+    }
+
+    /// Shared transfer logic: checks the caller is the owner, an approved spender, or an
+    /// operator, then moves ownership, clears any approvals on the token, and records the tx.
+    fn _transfer_nft(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        recipient: &Addr,
+        token_id: &str,
+        action: TxAction,
+    ) -> Result<TokenInfo<MintExt>, ContractError> {
+        self.assert_can_transfer(deps.storage)?;
+        let mut token = self.tokens.load(deps.storage, token_id)?;
+        self.check_can_send(deps.as_ref(), env, info, &token)?;
+
+        let from = token.owner.clone();
+        for approval in &token.approvals {
+            self.deindex_spender(deps.storage, &approval.spender, token_id);
+        }
+        token.approvals.clear();
+        token.owner = recipient.clone();
+        self.tokens.save(deps.storage, token_id, &token)?;
+
+        self.append_tx(
+            deps.storage,
+            action,
+            token_id,
+            Some(from),
+            Some(recipient.clone()),
+            &env.block,
+        )?;
+
+        Ok(token)
+    }
+
+    pub fn approve(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        let spender_addr = deps.api.addr_validate(&spender)?;
+        self._update_approval(deps, &env, &info, &spender_addr, &token_id, expires, true)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "approve")
+            .add_attribute("sender", info.sender)
+            .add_attribute("spender", spender)
+            .add_attribute("token_id", token_id))
+    }
+
+    pub fn revoke(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        spender: String,
+        token_id: String,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        let spender_addr = deps.api.addr_validate(&spender)?;
+        self._update_approval(deps, &env, &info, &spender_addr, &token_id, None, false)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "revoke")
+            .add_attribute("sender", info.sender)
+            .add_attribute("spender", spender)
+            .add_attribute("token_id", token_id))
+    }
+
+    fn _update_approval(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        spender: &Addr,
+        token_id: &str,
+        expires: Option<Expiration>,
+        add: bool,
+    ) -> Result<(), ContractError> {
+        self.assert_can_transfer(deps.storage)?;
+        let mut token = self.tokens.load(deps.storage, token_id)?;
+        self.check_can_approve(deps.as_ref(), env, info, &token)?;
+        let owner = token.owner.clone();
+
+        token.approvals.retain(|a| &a.spender != spender);
+        self.deindex_spender(deps.storage, spender, token_id);
+
+        if add {
+            let expires = expires.unwrap_or_default();
+            if expires.is_expired(&env.block) {
+                return Err(ContractError::Expired {});
+            }
+            token.approvals.push(Approval {
+                spender: spender.clone(),
+                expires,
+            });
+            self.index_spender(deps.storage, spender, token_id, expires)?;
+        }
+
+        self.tokens.save(deps.storage, token_id, &token)?;
+        self.append_tx(
+            deps.storage,
+            if add { TxAction::Approve } else { TxAction::Revoke },
+            token_id,
+            Some(owner),
+            Some(spender.clone()),
+            &env.block,
+        )?;
+        Ok(())
+    }
+
+    pub fn approve_all(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        operator: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        self.assert_can_transfer(deps.storage)?;
+        let operator_addr = deps.api.addr_validate(&operator)?;
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+        self.operators
+            .save(deps.storage, (&info.sender, &operator_addr), &expires)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "approve_all")
+            .add_attribute("sender", info.sender)
+            .add_attribute("operator", operator))
+    }
+
+    pub fn revoke_all(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        operator: String,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        self.assert_can_transfer(deps.storage)?;
+        let operator_addr = deps.api.addr_validate(&operator)?;
+        self.operators
+            .remove(deps.storage, (&info.sender, &operator_addr));
+
+        Ok(Response::new()
+            .add_attribute("action", "revoke_all")
+            .add_attribute("sender", info.sender)
+            .add_attribute("operator", operator))
+    }
+
+    pub fn burn(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        self.assert_can_transfer(deps.storage)?;
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        self.check_can_send(deps.as_ref(), &env, &info, &token)?;
+
+        for approval in &token.approvals {
+            self.deindex_spender(deps.storage, &approval.spender, &token_id);
+        }
+        self.tokens.remove(deps.storage, &token_id)?;
+        self.decrement_tokens(deps.storage)?;
+        self.append_tx(
+            deps.storage,
+            TxAction::Burn,
+            &token_id,
+            Some(token.owner),
+            None,
+            &env.block,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "burn")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", token_id))
+    }
+
+    /// Sets the contract-wide royalty used when a token has no per-token override.
+    pub fn set_default_royalty(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        payment_address: Option<String>,
+        royalty_rate_bps: u16,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+        if royalty_rate_bps > 10_000 {
+            return Err(ContractError::InvalidRoyaltyRate {});
+        }
+        if payment_address.is_none() && royalty_rate_bps != 0 {
+            return Err(ContractError::InvalidRoyaltyInput {});
+        }
+
+        let royalty = payment_address
+            .map(|addr| -> Result<_, ContractError> {
+                Ok(RoyaltyInfo {
+                    payment_address: deps.api.addr_validate(&addr)?,
+                    royalty_rate_bps,
+                })
+            })
+            .transpose()?;
+        self.default_royalty.save(deps.storage, &royalty)?;
+
+        Ok(Response::new().add_attribute("action", "set_default_royalty"))
+    }
+
+    /// Sets the contract-wide `ContractStatus` circuit breaker (minter-only).
+    pub fn set_contract_status_exec(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        level: ContractStatus,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+        self.set_contract_status(deps.storage, level)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_contract_status")
+            .add_attribute("level", format!("{level:?}")))
+    }
+
+    /// Escrows `token_id` (for a `Sale`) or the offered funds (for an `Offer`) under `id`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_swap(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        id: String,
+        token_id: String,
+        price: Coin,
+        payment_token: Option<String>,
+        swap_type: SwapType,
+        expires: Option<Expiration>,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        self.assert_can_transfer(deps.storage)?;
+        if self.swaps.has(deps.storage, &id) {
+            return Err(ContractError::SwapAlreadyExists {});
+        }
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::SwapExpired {});
+        }
+        // cw20-denominated swaps aren't escrowed or paid out anywhere yet; reject them rather
+        // than silently accepting a swap that can never collect payment
+        if payment_token.is_some() {
+            return Err(ContractError::UnsupportedSwapPaymentToken {});
+        }
+        let payment_token = payment_token
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+
+        // `creator` is whoever is entitled to the proceeds (`Sale`) or the refund (`Offer`) --
+        // for a `Sale` that's the token's actual owner, not whichever operator happened to call
+        // `CreateSwap` on their behalf
+        let creator = match swap_type {
+            SwapType::Sale => {
+                let token = self.tokens.load(deps.storage, &token_id)?;
+                // listing a token for sale is restricted to owner-or-operator, same as approvals
+                self.check_can_approve(deps.as_ref(), &env, &info, &token)?;
+                let owner = token.owner.clone();
+                self._transfer_nft(
+                    deps.branch(),
+                    &env,
+                    &info,
+                    &env.contract.address,
+                    &token_id,
+                    TxAction::Send,
+                )?;
+                owner
+            }
+            SwapType::Offer => {
+                // The offer creator escrows funds up front
+                assert_exact_funds(&info.funds, &price)?;
+                info.sender.clone()
+            }
+        };
+
+        let swap = Swap {
+            creator: creator.clone(),
+            token_id: token_id.clone(),
+            price,
+            payment_token,
+            swap_type,
+            expires,
+        };
+        self.swaps.save(deps.storage, &id, &swap)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "create_swap")
+            .add_attribute("id", id)
+            .add_attribute("creator", creator)
+            .add_attribute("token_id", token_id))
+    }
+
+    /// Atomically exchanges the escrowed NFT and payment for swap `id`, forwarding any royalty
+    /// owed on the token to its payout address.
+    pub fn finish_swap(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        id: String,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        self.assert_can_transfer(deps.storage)?;
+        let swap = self.swaps.load(deps.storage, &id)?;
+        if swap.expires.is_expired(&env.block) {
+            return Err(ContractError::SwapExpired {});
+        }
+
+        let royalty = self.royalty_info(deps.storage, &swap.token_id, swap.price.amount)?;
+        let mut messages = vec![];
+
+        match swap.swap_type {
+            SwapType::Sale => {
+                // `info.sender` is the buyer, paying `swap.price` for the token the contract
+                // has been escrowing since `CreateSwap`; proceeds go to `swap.creator`, the
+                // token's actual owner at listing time, regardless of who calls `FinishSwap`
+                assert_exact_funds(&info.funds, &swap.price)?;
+                let mut token = self.tokens.load(deps.storage, &swap.token_id)?;
+                token.owner = info.sender.clone();
+                self.tokens.save(deps.storage, &swap.token_id, &token)?;
+                self.append_tx(
+                    deps.storage,
+                    TxAction::Transfer,
+                    &swap.token_id,
+                    Some(env.contract.address.clone()),
+                    Some(info.sender.clone()),
+                    &env.block,
+                )?;
+                messages.extend(self._swap_payout(&swap.price, &swap.creator, &royalty));
+            }
+            SwapType::Offer => {
+                // `info.sender` must control the token being sold (owner/approved spender/
+                // operator); the offer creator (`swap.creator`) becomes the new owner, while the
+                // proceeds go to the token's actual owner, not whoever executed the transfer
+                let token = self.tokens.load(deps.storage, &swap.token_id)?;
+                self.check_can_send(deps.as_ref(), &env, &info, &token)?;
+                let seller = token.owner.clone();
+                self._transfer_nft(
+                    deps.branch(),
+                    &env,
+                    &info,
+                    &swap.creator,
+                    &swap.token_id,
+                    TxAction::Transfer,
+                )?;
+                messages.extend(self._swap_payout(&swap.price, &seller, &royalty));
+            }
+        }
+
+        self.swaps.remove(deps.storage, &id);
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "finish_swap")
+            .add_attribute("id", id))
+    }
+
+    /// Cancels swap `id`, refunding whichever side the creator escrowed.
+    pub fn cancel_swap(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        id: String,
+    ) -> Result<Response<ResponseExt>, ContractError> {
+        let swap = self.swaps.load(deps.storage, &id)?;
+        if swap.creator != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let mut messages = vec![];
+        match swap.swap_type {
+            SwapType::Sale => {
+                let mut token = self.tokens.load(deps.storage, &swap.token_id)?;
+                token.owner = swap.creator.clone();
+                self.tokens.save(deps.storage, &swap.token_id, &token)?;
+                self.append_tx(
+                    deps.storage,
+                    TxAction::Transfer,
+                    &swap.token_id,
+                    Some(env.contract.address.clone()),
+                    Some(swap.creator.clone()),
+                    &env.block,
+                )?;
+            }
+            SwapType::Offer => {
+                messages.push(BankMsg::Send {
+                    to_address: swap.creator.to_string(),
+                    amount: vec![swap.price.clone()],
+                });
+            }
+        }
+        self.swaps.remove(deps.storage, &id);
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "cancel_swap")
+            .add_attribute("id", id))
+    }
+
+    /// Splits a native `price` between the royalty payout (if any) and `recipient`, as `BankMsg`s.
+    fn _swap_payout(
+        &self,
+        price: &Coin,
+        recipient: &Addr,
+        royalty: &RoyaltyInfoResponse,
+    ) -> Vec<BankMsg> {
+        let mut messages = vec![];
+        let mut remaining = price.amount;
+        if let Some(payout) = &royalty.royalty_payment_address {
+            if !royalty.royalty_amount.is_zero() {
+                remaining -= royalty.royalty_amount;
+                messages.push(BankMsg::Send {
+                    to_address: payout.to_string(),
+                    amount: vec![Coin {
+                        denom: price.denom.clone(),
+                        amount: royalty.royalty_amount,
+                    }],
+                });
+            }
+        }
+        messages.push(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: price.denom.clone(),
+                amount: remaining,
+            }],
+        });
+        messages
+    }
+
+    pub(crate) fn check_can_send(
+        &self,
+        deps: cosmwasm_std::Deps,
+        env: &Env,
+        info: &MessageInfo,
+        token: &TokenInfo<MintExt>,
+    ) -> Result<(), ContractError> {
+        if token.owner == info.sender {
+            return Ok(());
+        }
+
+        if token
+            .approvals
+            .iter()
+            .any(|a| a.spender == info.sender && !a.is_expired(&env.block))
+        {
+            return Ok(());
+        }
+
+        match self
+            .operators
+            .may_load(deps.storage, (&token.owner, &info.sender))?
+        {
+            Some(expires) if !expires.is_expired(&env.block) => Ok(()),
+            _ => Err(ContractError::Unauthorized {}),
+        }
+    }
+
+    /// Unlike `check_can_send`, a token's approved spenders do not pass here: granting or
+    /// revoking an approval is restricted to the owner or an operator, matching upstream cw721.
+    pub(crate) fn check_can_approve(
+        &self,
+        deps: cosmwasm_std::Deps,
+        env: &Env,
+        info: &MessageInfo,
+        token: &TokenInfo<MintExt>,
+    ) -> Result<(), ContractError> {
+        if token.owner == info.sender {
+            return Ok(());
+        }
+
+        match self
+            .operators
+            .may_load(deps.storage, (&token.owner, &info.sender))?
+        {
+            Some(expires) if !expires.is_expired(&env.block) => Ok(()),
+            _ => Err(ContractError::Unauthorized {}),
+        }
+    }
+}
+
+/// Requires `funds` to consist of exactly one coin matching `expected`'s denom and amount,
+/// rejecting any shortfall as well as any extra coins sent alongside (in any denom) rather than
+/// silently keeping them unaccounted for.
+fn assert_exact_funds(funds: &[Coin], expected: &Coin) -> Result<(), ContractError> {
+    match funds {
+        [coin] if coin.denom == expected.denom && coin.amount == expected.amount => Ok(()),
+        _ => Err(ContractError::InvalidSwapFunds {}),
+    }
+}
+
+#[cfg(test)]
+mod swap_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{BankMsg, Coin, CosmosMsg, Empty};
+    use cw721::Expiration;
+
+    use crate::msg::{InstantiateMsg, MintMsg};
+    use crate::state::{Cw721Contract, SwapType};
+
+    type TestContract = Cw721Contract<'static, Empty, Empty, Empty, Empty, Empty>;
+
+    fn bank_sends(messages: &[cosmwasm_std::SubMsg<Empty>]) -> Vec<(&str, u128)> {
+        messages
+            .iter()
+            .filter_map(|m| match &m.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    Some((to_address.as_str(), amount[0].amount.u128()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn mint_token(contract: &TestContract, mut deps: cosmwasm_std::DepsMut, token_id: &str, owner: &str) {
+        contract
+            .instantiate(
+                deps.branch(),
+                mock_env(),
+                mock_info("minter", &[]),
+                InstantiateMsg {
+                    name: "test".to_string(),
+                    symbol: "TEST".to_string(),
+                    minter: "minter".to_string(),
+                },
+            )
+            .unwrap();
+        contract
+            .mint(
+                deps,
+                mock_env(),
+                mock_info("minter", &[]),
+                MintMsg {
+                    token_id: token_id.to_string(),
+                    owner: owner.to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                    royalty_payment_address: None,
+                    royalty_rate_bps: None,
+                    mint_run: None,
+                    mint_run_size: None,
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn sale_swap_happy_path() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "seller");
+
+        let price = Coin::new(100u128, "uusd");
+        contract
+            .create_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("seller", &[]),
+                "swap1".to_string(),
+                "1".to_string(),
+                price.clone(),
+                None,
+                SwapType::Sale,
+                None,
+            )
+            .unwrap();
+
+        // the NFT is escrowed with the contract as soon as the swap is created
+        let escrowed = contract.tokens.load(&deps.storage, "1").unwrap();
+        assert_eq!(escrowed.owner, mock_env().contract.address);
+
+        let res = contract
+            .finish_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("buyer", &[price]),
+                "swap1".to_string(),
+            )
+            .unwrap();
+
+        assert_eq!(bank_sends(&res.messages), vec![("seller", 100)]);
+        let token = contract.tokens.load(&deps.storage, "1").unwrap();
+        assert_eq!(token.owner, cosmwasm_std::Addr::unchecked("buyer"));
+        assert!(!contract.swaps.has(&deps.storage, "swap1"));
+    }
+
+    #[test]
+    fn sale_swap_cancel_refunds_nft() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "seller");
+
+        contract
+            .create_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("seller", &[]),
+                "swap1".to_string(),
+                "1".to_string(),
+                Coin::new(100u128, "uusd"),
+                None,
+                SwapType::Sale,
+                None,
+            )
+            .unwrap();
+
+        contract
+            .cancel_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("seller", &[]),
+                "swap1".to_string(),
+            )
+            .unwrap();
+
+        let token = contract.tokens.load(&deps.storage, "1").unwrap();
+        assert_eq!(token.owner, cosmwasm_std::Addr::unchecked("seller"));
+        assert!(!contract.swaps.has(&deps.storage, "swap1"));
+    }
+
+    #[test]
+    fn finish_swap_rejects_once_expired() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "seller");
+
+        let create_env = mock_env();
+        contract
+            .create_swap(
+                deps.as_mut(),
+                create_env.clone(),
+                mock_info("seller", &[]),
+                "swap1".to_string(),
+                "1".to_string(),
+                Coin::new(100u128, "uusd"),
+                None,
+                SwapType::Sale,
+                Some(Expiration::AtHeight(create_env.block.height + 1)),
+            )
+            .unwrap();
+
+        let mut later_env = create_env;
+        later_env.block.height += 10;
+
+        let err = contract
+            .finish_swap(
+                deps.as_mut(),
+                later_env,
+                mock_info("buyer", &[Coin::new(100u128, "uusd")]),
+                "swap1".to_string(),
+            )
+            .unwrap_err();
+        assert_eq!(err, crate::error::ContractError::SwapExpired {});
+    }
+
+    #[test]
+    fn finish_swap_splits_royalty_from_price() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        contract
+            .instantiate(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("minter", &[]),
+                InstantiateMsg {
+                    name: "test".to_string(),
+                    symbol: "TEST".to_string(),
+                    minter: "minter".to_string(),
+                },
+            )
+            .unwrap();
+        contract
+            .mint(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("minter", &[]),
+                MintMsg {
+                    token_id: "1".to_string(),
+                    owner: "seller".to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                    royalty_payment_address: Some("royalty_recipient".to_string()),
+                    royalty_rate_bps: Some(1000),
+                    mint_run: None,
+                    mint_run_size: None,
+                },
+            )
+            .unwrap();
+
+        let price = Coin::new(100u128, "uusd");
+        contract
+            .create_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("seller", &[]),
+                "swap1".to_string(),
+                "1".to_string(),
+                price.clone(),
+                None,
+                SwapType::Sale,
+                None,
+            )
+            .unwrap();
+
+        let res = contract
+            .finish_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("buyer", &[price]),
+                "swap1".to_string(),
+            )
+            .unwrap();
+
+        let mut sends = bank_sends(&res.messages);
+        sends.sort();
+        assert_eq!(
+            sends,
+            vec![("royalty_recipient", 10), ("seller", 90)]
+        );
+    }
+
+    #[test]
+    fn create_swap_rejects_cw20_payment_token() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "seller");
+
+        let err = contract
+            .create_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("seller", &[]),
+                "swap1".to_string(),
+                "1".to_string(),
+                Coin::new(100u128, "uusd"),
+                Some("cw20contract".to_string()),
+                SwapType::Sale,
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err, crate::error::ContractError::UnsupportedSwapPaymentToken {});
+    }
+
+    #[test]
+    fn create_swap_rejects_approved_spender_listing_on_owners_behalf() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "seller");
+        contract
+            .approve(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("seller", &[]),
+                "spender".to_string(),
+                "1".to_string(),
+                None,
+            )
+            .unwrap();
+
+        // an approved spender can move the token, but must not be able to list it for sale (and
+        // thereby collect the proceeds) on the owner's behalf
+        let err = contract
+            .create_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("spender", &[]),
+                "swap1".to_string(),
+                "1".to_string(),
+                Coin::new(100u128, "uusd"),
+                None,
+                SwapType::Sale,
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err, crate::error::ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn finish_swap_offer_pays_token_owner_not_the_finisher() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "seller");
+        contract
+            .approve(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("seller", &[]),
+                "spender".to_string(),
+                "1".to_string(),
+                None,
+            )
+            .unwrap();
+
+        let price = Coin::new(100u128, "uusd");
+        contract
+            .create_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("buyer", &[price.clone()]),
+                "offer1".to_string(),
+                "1".to_string(),
+                price,
+                None,
+                SwapType::Offer,
+                None,
+            )
+            .unwrap();
+
+        // the approved spender finishes the offer on the owner's behalf; the proceeds must go to
+        // the owner ("seller"), not to "spender"
+        let res = contract
+            .finish_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("spender", &[]),
+                "offer1".to_string(),
+            )
+            .unwrap();
+
+        assert_eq!(bank_sends(&res.messages), vec![("seller", 100)]);
+        let token = contract.tokens.load(&deps.storage, "1").unwrap();
+        assert_eq!(token.owner, cosmwasm_std::Addr::unchecked("buyer"));
+    }
+
+    #[test]
+    fn finish_swap_rejects_extra_coins_sent_alongside_price() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "seller");
+
+        let price = Coin::new(100u128, "uusd");
+        contract
+            .create_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("seller", &[]),
+                "swap1".to_string(),
+                "1".to_string(),
+                price.clone(),
+                None,
+                SwapType::Sale,
+                None,
+            )
+            .unwrap();
+
+        let err = contract
+            .finish_swap(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("buyer", &[price, Coin::new(5u128, "uscrt")]),
+                "swap1".to_string(),
+            )
+            .unwrap_err();
+        assert_eq!(err, crate::error::ContractError::InvalidSwapFunds {});
+    }
+}
+
+#[cfg(test)]
+mod royalty_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{Empty, Uint128};
+
+    use crate::msg::{InstantiateMsg, MintMsg};
+    use crate::state::Cw721Contract;
+
+    type TestContract = Cw721Contract<'static, Empty, Empty, Empty, Empty, Empty>;
+
+    fn instantiate(contract: &TestContract, deps: cosmwasm_std::DepsMut) {
+        contract
+            .instantiate(
+                deps,
+                mock_env(),
+                mock_info("minter", &[]),
+                InstantiateMsg {
+                    name: "test".to_string(),
+                    symbol: "TEST".to_string(),
+                    minter: "minter".to_string(),
+                },
+            )
+            .unwrap();
+    }
+
+    fn mint(
+        contract: &TestContract,
+        deps: cosmwasm_std::DepsMut,
+        token_id: &str,
+        royalty_payment_address: Option<&str>,
+        royalty_rate_bps: Option<u16>,
+    ) {
+        contract
+            .mint(
+                deps,
+                mock_env(),
+                mock_info("minter", &[]),
+                MintMsg {
+                    token_id: token_id.to_string(),
+                    owner: "owner".to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                    royalty_payment_address: royalty_payment_address.map(str::to_string),
+                    royalty_rate_bps,
+                    mint_run: None,
+                    mint_run_size: None,
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn royalty_info_falls_back_to_contract_default() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        instantiate(&contract, deps.as_mut());
+        mint(&contract, deps.as_mut(), "1", None, None);
+        contract
+            .set_default_royalty(
+                deps.as_mut(),
+                mock_info("minter", &[]),
+                Some("default_recipient".to_string()),
+                500,
+            )
+            .unwrap();
+
+        let royalty = contract
+            .royalty_info(&deps.storage, "1", Uint128::new(1000))
+            .unwrap();
+        assert_eq!(
+            royalty.royalty_payment_address,
+            Some(cosmwasm_std::Addr::unchecked("default_recipient"))
+        );
+        assert_eq!(royalty.royalty_amount, Uint128::new(50));
+    }
+
+    #[test]
+    fn royalty_info_per_token_override_wins_over_default() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        instantiate(&contract, deps.as_mut());
+        mint(
+            &contract,
+            deps.as_mut(),
+            "1",
+            Some("token_recipient"),
+            Some(1000),
+        );
+        contract
+            .set_default_royalty(
+                deps.as_mut(),
+                mock_info("minter", &[]),
+                Some("default_recipient".to_string()),
+                500,
+            )
+            .unwrap();
+
+        let royalty = contract
+            .royalty_info(&deps.storage, "1", Uint128::new(1000))
+            .unwrap();
+        assert_eq!(
+            royalty.royalty_payment_address,
+            Some(cosmwasm_std::Addr::unchecked("token_recipient"))
+        );
+        assert_eq!(royalty.royalty_amount, Uint128::new(100));
+    }
+
+    #[test]
+    fn royalty_info_is_zero_when_neither_is_set() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        instantiate(&contract, deps.as_mut());
+        mint(&contract, deps.as_mut(), "1", None, None);
+
+        let royalty = contract
+            .royalty_info(&deps.storage, "1", Uint128::new(1000))
+            .unwrap();
+        assert_eq!(royalty.royalty_payment_address, None);
+        assert_eq!(royalty.royalty_amount, Uint128::zero());
+    }
+
+    #[test]
+    fn mint_rejects_royalty_address_without_rate() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        instantiate(&contract, deps.as_mut());
+
+        let err = contract
+            .mint(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("minter", &[]),
+                MintMsg {
+                    token_id: "1".to_string(),
+                    owner: "owner".to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                    royalty_payment_address: Some("recipient".to_string()),
+                    royalty_rate_bps: None,
+                    mint_run: None,
+                    mint_run_size: None,
+                },
+            )
+            .unwrap_err();
+        assert_eq!(err, crate::error::ContractError::InvalidRoyaltyInput {});
+    }
+
+    #[test]
+    fn set_default_royalty_rejects_rate_without_address() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        instantiate(&contract, deps.as_mut());
+
+        let err = contract
+            .set_default_royalty(deps.as_mut(), mock_info("minter", &[]), None, 500)
+            .unwrap_err();
+        assert_eq!(err, crate::error::ContractError::InvalidRoyaltyInput {});
+    }
+
+    #[test]
+    fn set_default_royalty_allows_clearing_with_zero_rate() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        instantiate(&contract, deps.as_mut());
+
+        contract
+            .set_default_royalty(deps.as_mut(), mock_info("minter", &[]), None, 0)
+            .unwrap();
+        assert_eq!(contract.default_royalty.load(&deps.storage).unwrap(), None);
+    }
+}
+
+#[cfg(test)]
+mod tx_history_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::Empty;
+
+    use crate::msg::{InstantiateMsg, MintMsg};
+    use crate::state::{Cw721Contract, TxAction};
+
+    type TestContract = Cw721Contract<'static, Empty, Empty, Empty, Empty, Empty>;
+
+    fn instantiate_and_mint(contract: &TestContract, mut deps: cosmwasm_std::DepsMut, token_id: &str, owner: &str) {
+        contract
+            .instantiate(
+                deps.branch(),
+                mock_env(),
+                mock_info("minter", &[]),
+                InstantiateMsg {
+                    name: "test".to_string(),
+                    symbol: "TEST".to_string(),
+                    minter: "minter".to_string(),
+                },
+            )
+            .unwrap();
+        contract
+            .mint(
+                deps,
+                mock_env(),
+                mock_info("minter", &[]),
+                MintMsg {
+                    token_id: token_id.to_string(),
+                    owner: owner.to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                    royalty_payment_address: None,
+                    royalty_rate_bps: None,
+                    mint_run: None,
+                    mint_run_size: None,
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn token_transaction_history_is_newest_first() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        instantiate_and_mint(&contract, deps.as_mut(), "1", "alice");
+
+        contract
+            .transfer_nft(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "bob".to_string(),
+                "1".to_string(),
+            )
+            .unwrap();
+        contract
+            .transfer_nft(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("bob", &[]),
+                "carol".to_string(),
+                "1".to_string(),
+            )
+            .unwrap();
+
+        let txs = contract
+            .token_transaction_history(&deps.storage, "1", None, None)
+            .unwrap();
+        let actions: Vec<_> = txs.iter().map(|tx| tx.action).collect();
+        assert_eq!(
+            actions,
+            vec![TxAction::Transfer, TxAction::Transfer, TxAction::Mint]
+        );
+        assert_eq!(txs[0].to, Some(cosmwasm_std::Addr::unchecked("carol")));
+    }
+
+    #[test]
+    fn address_transaction_history_paginates_with_start_after() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        instantiate_and_mint(&contract, deps.as_mut(), "1", "alice");
+        instantiate_and_mint(&contract, deps.as_mut(), "2", "alice");
+        instantiate_and_mint(&contract, deps.as_mut(), "3", "alice");
+
+        let alice = cosmwasm_std::Addr::unchecked("alice");
+        let page1 = contract
+            .address_transaction_history(&deps.storage, &alice, None, Some(2))
+            .unwrap();
+        assert_eq!(page1.len(), 2);
+        assert_eq!(page1[0].token_id, "3");
+        assert_eq!(page1[1].token_id, "2");
+
+        let last_seq = contract
+            .tx_by_address
+            .prefix(&alice)
+            .keys(&deps.storage, None, None, cosmwasm_std::Order::Descending)
+            .nth(1)
+            .unwrap()
+            .unwrap();
+        let page2 = contract
+            .address_transaction_history(&deps.storage, &alice, Some(last_seq), Some(2))
+            .unwrap();
+        assert_eq!(page2.len(), 1);
+        assert_eq!(page2[0].token_id, "1");
+    }
+
+    #[test]
+    fn transaction_history_limit_is_capped_at_max() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        for i in 0..35 {
+            instantiate_and_mint(&contract, deps.as_mut(), &i.to_string(), "alice");
+        }
+
+        let txs = contract
+            .address_transaction_history(
+                &deps.storage,
+                &cosmwasm_std::Addr::unchecked("alice"),
+                None,
+                Some(1000),
+            )
+            .unwrap();
+        assert_eq!(txs.len(), 30);
+    }
+
+    #[test]
+    fn revoke_is_recorded_distinctly_from_approve() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        instantiate_and_mint(&contract, deps.as_mut(), "1", "alice");
+
+        contract
+            .approve(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "spender".to_string(),
+                "1".to_string(),
+                None,
+            )
+            .unwrap();
+        contract
+            .revoke(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "spender".to_string(),
+                "1".to_string(),
+            )
+            .unwrap();
+
+        let txs = contract
+            .token_transaction_history(&deps.storage, "1", None, None)
+            .unwrap();
+        let actions: Vec<_> = txs.iter().map(|tx| tx.action).collect();
+        assert_eq!(
+            actions,
+            vec![TxAction::Revoke, TxAction::Approve, TxAction::Mint]
+        );
+    }
+}
+
+#[cfg(test)]
+mod mint_run_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::Empty;
+
+    use crate::msg::{InstantiateMsg, MintMsg};
+    use crate::state::Cw721Contract;
+
+    type TestContract = Cw721Contract<'static, Empty, Empty, Empty, Empty, Empty>;
+
+    fn mint(
+        contract: &TestContract,
+        deps: cosmwasm_std::DepsMut,
+        token_id: &str,
+        mint_run_size: Option<u64>,
+    ) -> Result<cosmwasm_std::Response<Empty>, crate::error::ContractError> {
+        contract.mint(
+            deps,
+            mock_env(),
+            mock_info("minter", &[]),
+            MintMsg {
+                token_id: token_id.to_string(),
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: Empty {},
+                royalty_payment_address: None,
+                royalty_rate_bps: None,
+                mint_run: Some(1),
+                mint_run_size,
+            },
+        )
+    }
+
+    #[test]
+    fn mint_run_assigns_increasing_serial_numbers() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        contract
+            .instantiate(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("minter", &[]),
+                InstantiateMsg {
+                    name: "test".to_string(),
+                    symbol: "TEST".to_string(),
+                    minter: "minter".to_string(),
+                },
+            )
+            .unwrap();
+
+        mint(&contract, deps.as_mut(), "1", Some(2)).unwrap();
+        mint(&contract, deps.as_mut(), "2", Some(2)).unwrap();
+
+        let first = contract.tokens.load(&deps.storage, "1").unwrap();
+        let second = contract.tokens.load(&deps.storage, "2").unwrap();
+        assert_eq!(first.mint_run_info.unwrap().serial_number, 1);
+        assert_eq!(second.mint_run_info.unwrap().serial_number, 2);
+    }
+
+    #[test]
+    fn mint_run_rejects_mint_once_run_is_full() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        contract
+            .instantiate(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("minter", &[]),
+                InstantiateMsg {
+                    name: "test".to_string(),
+                    symbol: "TEST".to_string(),
+                    minter: "minter".to_string(),
+                },
+            )
+            .unwrap();
+
+        mint(&contract, deps.as_mut(), "1", Some(1)).unwrap();
+        let err = mint(&contract, deps.as_mut(), "2", Some(1)).unwrap_err();
+        assert!(matches!(err, crate::error::ContractError::Std(_)));
+        assert!(!contract.tokens.has(&deps.storage, "2"));
+    }
+
+    #[test]
+    fn mint_run_without_declared_size_never_rejects() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        contract
+            .instantiate(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("minter", &[]),
+                InstantiateMsg {
+                    name: "test".to_string(),
+                    symbol: "TEST".to_string(),
+                    minter: "minter".to_string(),
+                },
+            )
+            .unwrap();
+
+        mint(&contract, deps.as_mut(), "1", None).unwrap();
+        mint(&contract, deps.as_mut(), "2", None).unwrap();
+        let second = contract.tokens.load(&deps.storage, "2").unwrap();
+        assert_eq!(second.mint_run_info.unwrap().serial_number, 2);
+    }
+}
+
+#[cfg(test)]
+mod contract_status_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::Empty;
+
+    use crate::error::ContractError;
+    use crate::msg::{InstantiateMsg, MintMsg};
+    use crate::state::{ContractStatus, Cw721Contract};
+
+    type TestContract = Cw721Contract<'static, Empty, Empty, Empty, Empty, Empty>;
+
+    fn setup(contract: &TestContract, mut deps: cosmwasm_std::DepsMut) {
+        contract
+            .instantiate(
+                deps.branch(),
+                mock_env(),
+                mock_info("minter", &[]),
+                InstantiateMsg {
+                    name: "test".to_string(),
+                    symbol: "TEST".to_string(),
+                    minter: "minter".to_string(),
+                },
+            )
+            .unwrap();
+        contract
+            .mint(
+                deps,
+                mock_env(),
+                mock_info("minter", &[]),
+                MintMsg {
+                    token_id: "1".to_string(),
+                    owner: "alice".to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                    royalty_payment_address: None,
+                    royalty_rate_bps: None,
+                    mint_run: None,
+                    mint_run_size: None,
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn stop_transactions_blocks_transfer_but_allows_mint() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        setup(&contract, deps.as_mut());
+
+        contract
+            .set_contract_status_exec(
+                deps.as_mut(),
+                mock_info("minter", &[]),
+                ContractStatus::StopTransactions,
+            )
+            .unwrap();
+
+        let err = contract
+            .transfer_nft(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "bob".to_string(),
+                "1".to_string(),
+            )
+            .unwrap_err();
+        assert_eq!(err, ContractError::ContractStatusForbids {});
+
+        contract
+            .mint(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("minter", &[]),
+                MintMsg {
+                    token_id: "2".to_string(),
+                    owner: "alice".to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                    royalty_payment_address: None,
+                    royalty_rate_bps: None,
+                    mint_run: None,
+                    mint_run_size: None,
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn stop_all_blocks_mint_too() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        setup(&contract, deps.as_mut());
+
+        contract
+            .set_contract_status_exec(
+                deps.as_mut(),
+                mock_info("minter", &[]),
+                ContractStatus::StopAll,
+            )
+            .unwrap();
+
+        let err = contract
+            .mint(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("minter", &[]),
+                MintMsg {
+                    token_id: "2".to_string(),
+                    owner: "alice".to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                    royalty_payment_address: None,
+                    royalty_rate_bps: None,
+                    mint_run: None,
+                    mint_run_size: None,
+                },
+            )
+            .unwrap_err();
+        assert_eq!(err, ContractError::ContractStatusForbids {});
+
+        let err = contract
+            .transfer_nft(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "bob".to_string(),
+                "1".to_string(),
+            )
+            .unwrap_err();
+        assert_eq!(err, ContractError::ContractStatusForbids {});
+    }
+
+    #[test]
+    fn stop_transactions_blocks_approve_all() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        setup(&contract, deps.as_mut());
+
+        contract
+            .set_contract_status_exec(
+                deps.as_mut(),
+                mock_info("minter", &[]),
+                ContractStatus::StopTransactions,
+            )
+            .unwrap();
+
+        let err = contract
+            .approve_all(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "operator".to_string(),
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err, ContractError::ContractStatusForbids {});
+    }
+
+    #[test]
+    fn set_contract_status_is_minter_only() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        setup(&contract, deps.as_mut());
+
+        let err = contract
+            .set_contract_status_exec(
+                deps.as_mut(),
+                mock_info("alice", &[]),
+                ContractStatus::StopAll,
+            )
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn normal_status_allows_transfers_and_mints() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        setup(&contract, deps.as_mut());
+
+        assert_eq!(
+            contract.contract_status(&deps.storage).unwrap(),
+            ContractStatus::Normal
+        );
+        contract
+            .transfer_nft(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "bob".to_string(),
+                "1".to_string(),
+            )
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod spender_index_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::Empty;
+    use cw721::Expiration;
+
+    use crate::msg::{InstantiateMsg, MintMsg};
+    use crate::state::Cw721Contract;
+
+    type TestContract = Cw721Contract<'static, Empty, Empty, Empty, Empty, Empty>;
+
+    fn mint_token(contract: &TestContract, mut deps: cosmwasm_std::DepsMut, token_id: &str, owner: &str) {
+        contract
+            .instantiate(
+                deps.branch(),
+                mock_env(),
+                mock_info("minter", &[]),
+                InstantiateMsg {
+                    name: "test".to_string(),
+                    symbol: "TEST".to_string(),
+                    minter: "minter".to_string(),
+                },
+            )
+            .unwrap();
+        contract
+            .mint(
+                deps,
+                mock_env(),
+                mock_info("minter", &[]),
+                MintMsg {
+                    token_id: token_id.to_string(),
+                    owner: owner.to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                    royalty_payment_address: None,
+                    royalty_rate_bps: None,
+                    mint_run: None,
+                    mint_run_size: None,
+                },
+            )
+            .unwrap();
+    }
+
+    fn tokens_by_spender(
+        contract: &TestContract,
+        storage: &dyn cosmwasm_std::Storage,
+        spender: &str,
+    ) -> Vec<String> {
+        contract
+            .tokens_by_spender(
+                storage,
+                &mock_env().block,
+                &cosmwasm_std::Addr::unchecked(spender),
+                None,
+                None,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn tokens_by_spender_reflects_approve_and_revoke() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "alice");
+        mint_token(&contract, deps.as_mut(), "2", "alice");
+
+        contract
+            .approve(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "spender".to_string(),
+                "1".to_string(),
+                None,
+            )
+            .unwrap();
+        contract
+            .approve(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "spender".to_string(),
+                "2".to_string(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(tokens_by_spender(&contract, &deps.storage, "spender"), vec!["1", "2"]);
+
+        contract
+            .revoke(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "spender".to_string(),
+                "1".to_string(),
+            )
+            .unwrap();
+        assert_eq!(tokens_by_spender(&contract, &deps.storage, "spender"), vec!["2"]);
+    }
+
+    #[test]
+    fn tokens_by_spender_drops_entry_on_transfer_and_burn() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "alice");
+        mint_token(&contract, deps.as_mut(), "2", "alice");
+
+        contract
+            .approve(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "spender".to_string(),
+                "1".to_string(),
+                None,
+            )
+            .unwrap();
+        contract
+            .approve(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "spender".to_string(),
+                "2".to_string(),
+                None,
+            )
+            .unwrap();
+
+        // transferring a token clears its approvals, so the spender index must drop it too
+        contract
+            .transfer_nft(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "bob".to_string(),
+                "1".to_string(),
+            )
+            .unwrap();
+        assert_eq!(tokens_by_spender(&contract, &deps.storage, "spender"), vec!["2"]);
+
+        contract
+            .burn(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("alice", &[]),
+                "2".to_string(),
+            )
+            .unwrap();
+        assert!(tokens_by_spender(&contract, &deps.storage, "spender").is_empty());
+    }
+
+    #[test]
+    fn tokens_by_spender_filters_out_expired_approvals() {
+        let contract = TestContract::default();
+        let mut deps = mock_dependencies();
+        mint_token(&contract, deps.as_mut(), "1", "alice");
+
+        let env = mock_env();
+        contract
+            .approve(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("alice", &[]),
+                "spender".to_string(),
+                "1".to_string(),
+                Some(Expiration::AtHeight(env.block.height + 1)),
+            )
+            .unwrap();
+
+        // the hand-maintained index still has the raw entry, but the query must filter it
+        // once it's expired
+        assert_eq!(tokens_by_spender(&contract, &deps.storage, "spender"), vec!["1"]);
+
+        let mut later_env = env;
+        later_env.block.height += 10;
+        let tokens = contract
+            .tokens_by_spender(
+                &deps.storage,
+                &later_env.block,
+                &cosmwasm_std::Addr::unchecked("spender"),
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(tokens.is_empty());
+    }
+}
@@ -0,0 +1,9 @@
+pub mod error;
+pub mod execute;
+pub mod msg;
+pub mod query;
+pub mod state;
+
+pub use crate::error::ContractError;
+pub use crate::msg::{ExecuteMsg, InstantiateMsg, MintMsg, QueryMsg};
+pub use crate::state::Cw721Contract;
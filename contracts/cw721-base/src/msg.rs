@@ -0,0 +1,139 @@
+use cosmwasm_std::{Binary, Coin, Uint128};
+use cw721::Expiration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{ContractStatus, SwapType};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    pub minter: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintMsg<T> {
+    pub token_id: String,
+    pub owner: String,
+    pub token_uri: Option<String>,
+    pub extension: T,
+    /// Per-token royalty override; omit to fall back to the contract-wide default royalty
+    pub royalty_payment_address: Option<String>,
+    pub royalty_rate_bps: Option<u16>,
+    /// Mint run this token belongs to, if the collection tracks numbered editions
+    pub mint_run: Option<u32>,
+    /// Declared size of `mint_run`; minting past it is rejected
+    pub mint_run_size: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg<T, E> {
+    TransferNft {
+        recipient: String,
+        token_id: String,
+    },
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    Revoke {
+        spender: String,
+        token_id: String,
+    },
+    ApproveAll {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    RevokeAll {
+        operator: String,
+    },
+    Burn {
+        token_id: String,
+    },
+    Mint(MintMsg<T>),
+    /// Sets the contract-wide royalty used when a token carries no per-token override
+    SetDefaultRoyalty {
+        payment_address: Option<String>,
+        royalty_rate_bps: u16,
+    },
+    /// Sets the contract-wide circuit breaker (minter-only)
+    SetContractStatus {
+        level: ContractStatus,
+    },
+    /// Escrows `token_id` (for a `Sale`) or the offered funds (for an `Offer`) under `id`
+    CreateSwap {
+        id: String,
+        token_id: String,
+        price: Coin,
+        /// Reserved for a future cw20 payment path; must be `None` today or the swap is rejected
+        payment_token: Option<String>,
+        swap_type: SwapType,
+        expires: Option<Expiration>,
+    },
+    /// Atomically exchanges the escrowed NFT and payment for swap `id`, forwarding any royalty
+    /// owed on the token to its payout address
+    FinishSwap {
+        id: String,
+    },
+    /// Cancels swap `id`, refunding whichever side the creator escrowed
+    CancelSwap {
+        id: String,
+    },
+    Extension {
+        msg: E,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg<Q> {
+    /// Returns the payout address and amount owed for `token_id` selling at `sale_price`
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    /// Returns `address`'s transaction history, newest-first
+    TransactionHistory {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns `token_id`'s transaction history, newest-first
+    TokenTransactionHistory {
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns `token_id`'s mint-run/edition metadata, if any
+    MintRunInfo {
+        token_id: String,
+    },
+    /// Returns the current `ContractStatus` circuit breaker level
+    ContractStatus {},
+    /// Returns up to `limit` token IDs that `spender` holds a non-expired approval over
+    TokensBySpender {
+        spender: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the `Swap` stored under `id`
+    SwapDetails {
+        id: String,
+    },
+    /// Returns up to `limit` swaps, ordered by swap id
+    ListSwaps {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    Extension {
+        msg: Q,
+    },
+}
@@ -0,0 +1,84 @@
+use cosmwasm_std::{to_json_binary, Binary, Deps, Env, StdResult};
+use cw721::CustomMsg;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::msg::QueryMsg;
+use crate::state::{Cw721Contract, Swap, Tx};
+
+/// Response for the `TransactionHistory` and `TokenTransactionHistory` queries.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransactionHistoryResponse {
+    pub txs: Vec<Tx>,
+}
+
+/// Response for the `TokensBySpender` query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokensBySpenderResponse {
+    pub tokens: Vec<String>,
+}
+
+/// Response for the `ListSwaps` query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListSwapsResponse {
+    pub swaps: Vec<Swap>,
+}
+
+impl<'a, MintExt, ResponseExt, InstantiateExt, ExecuteExt, QueryExt>
+    Cw721Contract<'a, MintExt, ResponseExt, InstantiateExt, ExecuteExt, QueryExt>
+where
+    MintExt: Serialize + DeserializeOwned + Clone,
+    InstantiateExt: CustomMsg + DeserializeOwned,
+    ExecuteExt: CustomMsg,
+    QueryExt: CustomMsg,
+{
+    pub fn query(&self, deps: Deps, env: Env, msg: QueryMsg<QueryExt>) -> StdResult<Binary> {
+        match msg {
+            QueryMsg::RoyaltyInfo {
+                token_id,
+                sale_price,
+            } => to_json_binary(&self.royalty_info(deps.storage, &token_id, sale_price)?),
+            QueryMsg::TransactionHistory {
+                address,
+                start_after,
+                limit,
+            } => {
+                let address = deps.api.addr_validate(&address)?;
+                let txs =
+                    self.address_transaction_history(deps.storage, &address, start_after, limit)?;
+                to_json_binary(&TransactionHistoryResponse { txs })
+            }
+            QueryMsg::TokenTransactionHistory {
+                token_id,
+                start_after,
+                limit,
+            } => {
+                let txs =
+                    self.token_transaction_history(deps.storage, &token_id, start_after, limit)?;
+                to_json_binary(&TransactionHistoryResponse { txs })
+            }
+            QueryMsg::MintRunInfo { token_id } => {
+                let token = self.tokens.load(deps.storage, &token_id)?;
+                to_json_binary(&token.mint_run_info)
+            }
+            QueryMsg::ContractStatus {} => to_json_binary(&self.contract_status(deps.storage)?),
+            QueryMsg::TokensBySpender {
+                spender,
+                start_after,
+                limit,
+            } => {
+                let spender = deps.api.addr_validate(&spender)?;
+                let tokens =
+                    self.tokens_by_spender(deps.storage, &env.block, &spender, start_after, limit)?;
+                to_json_binary(&TokensBySpenderResponse { tokens })
+            }
+            QueryMsg::SwapDetails { id } => to_json_binary(&self.swap(deps.storage, &id)?),
+            QueryMsg::ListSwaps { start_after, limit } => {
+                let swaps = self.list_swaps(deps.storage, start_after, limit)?;
+                to_json_binary(&ListSwapsResponse { swaps })
+            }
+            QueryMsg::Extension { msg: _ } => to_json_binary(&cosmwasm_std::Empty {}),
+        }
+    }
+}
@@ -3,10 +3,19 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
-use cosmwasm_std::{Addr, BlockInfo, CustomMsg, StdResult, Storage};
+use cosmwasm_std::{
+    Addr, BlockInfo, Coin, CustomMsg, Order, StdResult, Storage, Timestamp, Uint128,
+};
 
 use cw721::{ContractInfoResponse, Expiration};
-use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+
+use crate::error::ContractError;
+
+/// Default and maximum page sizes for the paginated queries (transaction history, tokens by
+/// spender, swaps).
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
 pub struct Cw721Contract<'a, MintExt, ResponseExt, InstantiateExt, ExecuteExt, QueryExt>
 where
@@ -21,6 +30,31 @@ where
     /// Stored as (granter, operator) giving operator full control over granter's account
     pub operators: Map<'a, (&'a Addr, &'a Addr), Expiration>,
     pub tokens: IndexedMap<'a, &'a str, TokenInfo<MintExt>, TokenIndexes<'a, MintExt>>,
+    /// Contract-wide royalty applied to tokens that don't carry their own `TokenInfo::royalty`
+    pub default_royalty: Item<'a, Option<RoyaltyInfo>>,
+    /// Monotonic counter assigning each recorded `Tx` a unique, ever-increasing sequence number
+    pub tx_count: Item<'a, u64>,
+    /// Transaction-history ledger keyed by (token_id, sequence)
+    pub tx_history: Map<'a, (&'a str, u64), Tx>,
+    /// Secondary index from (address, sequence) to the token_id of that ledger entry, so an
+    /// address's activity can be enumerated without scanning every token's history
+    pub tx_by_address: Map<'a, (&'a Addr, u64), String>,
+    /// Per-run counter tracking how many serial numbers have been assigned within each mint run
+    pub mint_run_counters: Map<'a, u32, u64>,
+    /// Circuit breaker gating mutating entry points; defaults to `ContractStatus::Normal`
+    pub contract_status: Item<'a, ContractStatus>,
+    /// Fan-out index from (spender, token_id) to that approval's expiration.
+    ///
+    /// This is deliberately a hand-maintained `Map`, not a `cw_storage_plus::MultiIndex` on
+    /// `tokens`: a `MultiIndex` derives exactly one index key per stored item from a pure
+    /// function of that item, but a single `TokenInfo` can carry many approvals at once
+    /// (`TokenInfo::approvals: Vec<Approval>`), so there is no one index key to derive. Every
+    /// call site that adds or removes an `Approval` (`_update_approval`, `_transfer_nft`, `burn`)
+    /// must keep this map in sync via `index_spender`/`deindex_spender` -- there is no automatic
+    /// anti-drift guarantee the way there is for `tokens`' `owner` index.
+    pub spender_index: Map<'a, (&'a Addr, &'a str), Expiration>,
+    /// Escrowed fixed-price sales and offers, keyed by swap id
+    pub swaps: Map<'a, &'a str, Swap>,
 
     pub(crate) _custom_response: PhantomData<ResponseExt>,
     pub(crate) _custom_instantiate: PhantomData<InstantiateExt>,
@@ -44,6 +78,14 @@ where
             "operators",
             "tokens",
             "tokens__owner",
+            "default_royalty",
+            "tx_count",
+            "tx_history",
+            "tx_by_address",
+            "mint_run_counters",
+            "contract_status",
+            "spender_index",
+            "swaps",
         )
     }
 }
@@ -63,6 +105,14 @@ where
         operator_key: &'a str,
         tokens_key: &'a str,
         tokens_owner_key: &'a str,
+        default_royalty_key: &'a str,
+        tx_count_key: &'a str,
+        tx_history_key: &'a str,
+        tx_by_address_key: &'a str,
+        mint_run_counters_key: &'a str,
+        contract_status_key: &'a str,
+        spender_index_key: &'a str,
+        swaps_key: &'a str,
     ) -> Self {
         let indexes = TokenIndexes {
             owner: MultiIndex::new(token_owner_idx, tokens_key, tokens_owner_key),
@@ -73,6 +123,14 @@ where
             token_count: Item::new(token_count_key),
             operators: Map::new(operator_key),
             tokens: IndexedMap::new(tokens_key, indexes),
+            default_royalty: Item::new(default_royalty_key),
+            tx_count: Item::new(tx_count_key),
+            tx_history: Map::new(tx_history_key),
+            tx_by_address: Map::new(tx_by_address_key),
+            mint_run_counters: Map::new(mint_run_counters_key),
+            contract_status: Item::new(contract_status_key),
+            spender_index: Map::new(spender_index_key),
+            swaps: Map::new(swaps_key),
             _custom_response: PhantomData,
             _custom_execute: PhantomData,
             _custom_query: PhantomData,
@@ -95,6 +153,228 @@ where
         self.token_count.save(storage, &val)?;
         Ok(val)
     }
+
+    /// Computes the royalty payout for `token_id` at `sale_price`, falling back to the
+    /// contract-wide default royalty when the token has no override, and to a zero-amount,
+    /// no-recipient result when neither is set.
+    pub fn royalty_info(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str,
+        sale_price: Uint128,
+    ) -> StdResult<RoyaltyInfoResponse> {
+        let token = self.tokens.load(storage, token_id)?;
+        let royalty = match token.royalty {
+            Some(royalty) => Some(royalty),
+            None => self.default_royalty.may_load(storage)?.flatten(),
+        };
+
+        let royalty = match royalty {
+            Some(royalty) => royalty,
+            None => return Ok(RoyaltyInfoResponse::none()),
+        };
+
+        let royalty_amount = sale_price
+            .checked_mul(Uint128::from(royalty.royalty_rate_bps))?
+            .checked_div(Uint128::from(10_000u128))?;
+
+        Ok(RoyaltyInfoResponse {
+            royalty_payment_address: Some(royalty.payment_address),
+            royalty_amount,
+        })
+    }
+
+    fn next_tx_seq(&self, storage: &mut dyn Storage) -> StdResult<u64> {
+        let seq = self.tx_count.may_load(storage)?.unwrap_or_default() + 1;
+        self.tx_count.save(storage, &seq)?;
+        Ok(seq)
+    }
+
+    /// Appends a `Tx` to the ledger and indexes it against every address it touches. Every
+    /// mutating entry point (mint/transfer/send/burn/approve) should call this once it succeeds.
+    pub fn append_tx(
+        &self,
+        storage: &mut dyn Storage,
+        action: TxAction,
+        token_id: &str,
+        from: Option<Addr>,
+        to: Option<Addr>,
+        block: &BlockInfo,
+    ) -> StdResult<u64> {
+        let seq = self.next_tx_seq(storage)?;
+        let tx = Tx {
+            action,
+            token_id: token_id.to_string(),
+            from: from.clone(),
+            to: to.clone(),
+            block_height: block.height,
+            time: block.time,
+        };
+        self.tx_history.save(storage, (token_id, seq), &tx)?;
+        for addr in [from, to].into_iter().flatten() {
+            self.tx_by_address
+                .save(storage, (&addr, seq), &token_id.to_string())?;
+        }
+        Ok(seq)
+    }
+
+    /// Returns up to `limit` transactions for `token_id`, newest-first.
+    pub fn token_transaction_history(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<Tx>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let max = start_after.map(Bound::exclusive);
+        self.tx_history
+            .prefix(token_id)
+            .range(storage, None, max, Order::Descending)
+            .take(limit)
+            .map(|item| item.map(|(_, tx)| tx))
+            .collect()
+    }
+
+    /// Returns up to `limit` transactions touching `address`, newest-first.
+    pub fn address_transaction_history(
+        &self,
+        storage: &dyn Storage,
+        address: &Addr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<Tx>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let max = start_after.map(Bound::exclusive);
+        self.tx_by_address
+            .prefix(address)
+            .range(storage, None, max, Order::Descending)
+            .take(limit)
+            .map(|item| {
+                let (seq, token_id) = item?;
+                self.tx_history.load(storage, (token_id.as_str(), seq))
+            })
+            .collect()
+    }
+
+    /// Assigns the next monotonically increasing serial number within `mint_run`, rejecting the
+    /// mint once `run_size` tokens have already been assigned.
+    pub fn next_serial_number(
+        &self,
+        storage: &mut dyn Storage,
+        mint_run: u32,
+        run_size: Option<u64>,
+    ) -> StdResult<u64> {
+        let minted_so_far = self.mint_run_counters.may_load(storage, mint_run)?.unwrap_or_default();
+        if let Some(run_size) = run_size {
+            if minted_so_far >= run_size {
+                return Err(cosmwasm_std::StdError::generic_err(format!(
+                    "mint run {mint_run} is full: {run_size} tokens already minted"
+                )));
+            }
+        }
+        let serial_number = minted_so_far + 1;
+        self.mint_run_counters.save(storage, mint_run, &serial_number)?;
+        Ok(serial_number)
+    }
+
+    /// Returns the current `ContractStatus`, defaulting to `Normal` until explicitly set.
+    pub fn contract_status(&self, storage: &dyn Storage) -> StdResult<ContractStatus> {
+        Ok(self
+            .contract_status
+            .may_load(storage)?
+            .unwrap_or(ContractStatus::Normal))
+    }
+
+    /// Errors unless the contract currently allows transfers/sends/approvals/burns.
+    pub fn assert_can_transfer(&self, storage: &dyn Storage) -> Result<(), ContractError> {
+        match self.contract_status(storage)? {
+            ContractStatus::Normal => Ok(()),
+            ContractStatus::StopTransactions | ContractStatus::StopAll => {
+                Err(ContractError::ContractStatusForbids {})
+            }
+        }
+    }
+
+    /// Errors unless the contract currently allows minting.
+    pub fn assert_can_mint(&self, storage: &dyn Storage) -> Result<(), ContractError> {
+        match self.contract_status(storage)? {
+            ContractStatus::Normal | ContractStatus::StopTransactions => Ok(()),
+            ContractStatus::StopAll => Err(ContractError::ContractStatusForbids {}),
+        }
+    }
+
+    /// Sets the contract-wide `ContractStatus` circuit breaker.
+    pub fn set_contract_status(
+        &self,
+        storage: &mut dyn Storage,
+        status: ContractStatus,
+    ) -> StdResult<()> {
+        self.contract_status.save(storage, &status)
+    }
+
+    /// Records that `spender` may move `token_id` until `expires`. Call once per entry of
+    /// `TokenInfo::approvals` whenever an approval is granted.
+    pub fn index_spender(
+        &self,
+        storage: &mut dyn Storage,
+        spender: &Addr,
+        token_id: &str,
+        expires: Expiration,
+    ) -> StdResult<()> {
+        self.spender_index.save(storage, (spender, token_id), &expires)
+    }
+
+    /// Removes `spender`'s entry for `token_id`. Call whenever an approval is revoked or cleared
+    /// (transfer, send, burn, explicit revoke).
+    pub fn deindex_spender(&self, storage: &mut dyn Storage, spender: &Addr, token_id: &str) {
+        self.spender_index.remove(storage, (spender, token_id));
+    }
+
+    /// Returns up to `limit` token IDs that `spender` holds a non-expired approval over.
+    pub fn tokens_by_spender(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        spender: &Addr,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<String>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let min = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+        self.spender_index
+            .prefix(spender)
+            .range(storage, min, None, Order::Ascending)
+            .filter(|item| {
+                item.as_ref()
+                    .map(|(_, expires)| !expires.is_expired(block))
+                    .unwrap_or(true)
+            })
+            .take(limit)
+            .map(|item| item.map(|(token_id, _)| token_id))
+            .collect()
+    }
+
+    /// Loads a single swap by id, as returned by the `SwapDetails` query.
+    pub fn swap(&self, storage: &dyn Storage, swap_id: &str) -> StdResult<Swap> {
+        self.swaps.load(storage, swap_id)
+    }
+
+    /// Returns up to `limit` swaps, ordered by swap id, as returned by the `ListSwaps` query.
+    pub fn list_swaps(
+        &self,
+        storage: &dyn Storage,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<Swap>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let min = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+        self.swaps
+            .range(storage, min, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, swap)| swap))
+            .collect()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -111,6 +391,104 @@ pub struct TokenInfo<MintExt> {
 
     /// You can add any custom metadata here when you extend cw721-base
     pub extension: MintExt,
+
+    /// Per-token royalty override. Falls back to `Cw721Contract::default_royalty` when `None`.
+    pub royalty: Option<RoyaltyInfo>,
+
+    /// Edition metadata for tokens minted as part of a numbered mint run (e.g. "#7 of 100")
+    pub mint_run_info: Option<MintRunInfo>,
+}
+
+/// Edition metadata identifying a token's position within a mint run.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintRunInfo {
+    pub minter: Addr,
+    /// Which mint run this token belongs to, if runs are tracked for this collection
+    pub mint_run: Option<u32>,
+    /// This token's position within the run, assigned monotonically as the run is minted
+    pub serial_number: u64,
+    /// How many tokens had been minted in this run as of this token's mint, if runs are tracked
+    pub quantity_minted_in_run: Option<u64>,
+}
+
+/// A royalty payout owed to `payment_address`, expressed in basis points (0-10000) of the sale price.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltyInfo {
+    pub payment_address: Addr,
+    pub royalty_rate_bps: u16,
+}
+
+/// Response for the `RoyaltyInfo { token_id, sale_price }` query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltyInfoResponse {
+    pub royalty_payment_address: Option<Addr>,
+    pub royalty_amount: Uint128,
+}
+
+impl RoyaltyInfoResponse {
+    fn none() -> Self {
+        Self {
+            royalty_payment_address: None,
+            royalty_amount: Uint128::zero(),
+        }
+    }
+}
+
+/// Contract-wide circuit breaker set via the `SetContractStatus` execute and read back through
+/// the `ContractStatus` query.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+pub enum ContractStatus {
+    /// All entry points behave normally
+    Normal,
+    /// Transfers, sends, approvals and burns are blocked; queries and admin ops still work
+    StopTransactions,
+    /// Everything `StopTransactions` blocks, plus minting
+    StopAll,
+}
+
+/// Whether a `Swap` is a listing the creator is selling, or an offer the creator is making on
+/// someone else's token.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+pub enum SwapType {
+    Sale,
+    Offer,
+}
+
+/// A fixed-price sale or offer escrowed by the contract. `CreateSwap` escrows the NFT (for a
+/// `Sale`) or the payment (for an `Offer`); `FinishSwap` atomically swaps them, forwarding any
+/// royalty owed on the token; `CancelSwap` refunds whichever side is escrowed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Swap {
+    pub creator: Addr,
+    pub token_id: String,
+    pub price: Coin,
+    /// Reserved for a future cw20 payment path; `CreateSwap` currently rejects `Some(_)`
+    pub payment_token: Option<Addr>,
+    pub swap_type: SwapType,
+    pub expires: Expiration,
+}
+
+/// The kind of mutating action a `Tx` ledger entry records.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+pub enum TxAction {
+    Mint,
+    Transfer,
+    Send,
+    Burn,
+    Approve,
+    Revoke,
+}
+
+/// A single entry in the on-chain transaction-history ledger, returned by the
+/// `TransactionHistory` and `TokenTransactionHistory` queries.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Tx {
+    pub action: TxAction,
+    pub token_id: String,
+    pub from: Option<Addr>,
+    pub to: Option<Addr>,
+    pub block_height: u64,
+    pub time: Timestamp,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]